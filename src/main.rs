@@ -3,18 +3,52 @@ use std::env;
 use std::error;
 use std::fmt;
 use std::fs;
+use std::process;
 
 type Result<T> = std::result::Result<T, Box<error::Error>>;
 
 #[derive(Debug)]
 enum Error {
     Usage,
-    Format,
+    /// A parse failure, carrying the 1-based line number, the offending line
+    /// and a human-readable message so the location can be shown to the user.
+    Parse {
+        line: usize,
+        /// 1-based column the caret should point at.
+        col: usize,
+        /// Number of columns to underline from `col`.
+        span: usize,
+        text: String,
+        msg: String,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Error::Usage => write!(f, "invalid usage"),
+            Error::Parse {
+                line,
+                col,
+                span,
+                text,
+                msg,
+            } => {
+                let gutter = line.to_string();
+                let pad = " ".repeat(gutter.len());
+                writeln!(f, "error: {}", msg)?;
+                writeln!(f, "{}--> line {}:{}", pad, line, col)?;
+                writeln!(f, "{} |", pad)?;
+                writeln!(f, "{} | {}", gutter, text)?;
+                write!(
+                    f,
+                    "{} | {}{}",
+                    pad,
+                    " ".repeat(col.saturating_sub(1)),
+                    "^".repeat((*span).max(1))
+                )
+            }
+        }
     }
 }
 
@@ -25,34 +59,340 @@ impl error::Error for Error {
 }
 
 fn usage() {
-    println!("usage: vcfdedup <vcf>");
+    println!("usage: vcfdedup [--key uid|n|fn] <vcf>");
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct VcardEntry {
-    lines: Vec<String>,
+/// Decode RFC 6868 caret escaping in a parameter value.
+///
+/// `^n` becomes a newline, `^^` a caret and `^'` a double quote; a trailing
+/// lone `^` (or any other `^x` sequence) is passed through unchanged.
+fn decode_param_value(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '^' {
+            match chars.peek() {
+                Some('n') => {
+                    out.push('\n');
+                    chars.next();
+                }
+                Some('^') => {
+                    out.push('^');
+                    chars.next();
+                }
+                Some('\'') => {
+                    out.push('"');
+                    chars.next();
+                }
+                _ => out.push('^'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
 }
 
-impl VcardEntry {
-    fn new() -> Self {
-        VcardEntry { lines: Vec::new() }
+/// Decode a Quoted-Printable value (RFC 2045, as used by vCard 2.1).
+///
+/// `=XX` hex escapes become their byte; everything else is passed through.
+/// Soft line breaks (a trailing `=`) are expected to already be joined away by
+/// the parser before this runs.
+fn decode_quoted_printable(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'='
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            if let Ok(v) = u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
     }
+    String::from_utf8_lossy(&out).into_owned()
+}
 
-    fn push(&mut self, s: &str) {
-        assert!(s.starts_with(' ') || self.lines.is_empty());
-        self.lines.push(s.to_owned());
+/// Decode a standard Base64 value, ignoring non-alphabet bytes and padding.
+fn decode_base64(s: &str) -> Vec<u8> {
+    fn sextet(c: u8) -> Option<u8> {
+        match c {
+            b'A'..=b'Z' => Some(c - b'A'),
+            b'a'..=b'z' => Some(c - b'a' + 26),
+            b'0'..=b'9' => Some(c - b'0' + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let mut out = Vec::new();
+    let mut buf = 0u32;
+    let mut bits = 0u32;
+    for &c in s.as_bytes() {
+        if c == b'=' {
+            break;
+        }
+        if let Some(v) = sextet(c) {
+            buf = (buf << 6) | u32::from(v);
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((buf >> bits) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// The value of the `ENCODING` parameter, upper-cased, if present.
+fn encoding(params: &[(String, Vec<String>)]) -> Option<String> {
+    params
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("ENCODING"))
+        .and_then(|(_, vs)| vs.first())
+        .map(|v| v.to_uppercase())
+}
+
+/// Split `s` on `sep`, ignoring separators inside double-quoted runs.
+fn split_unquoted(s: &str, sep: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                cur.push(c);
+            }
+            _ if c == sep && !quoted => {
+                parts.push(cur.clone());
+                cur.clear();
+            }
+            _ => cur.push(c),
+        }
+    }
+    parts.push(cur);
+    parts
+}
+
+/// Fold a logical line to physical lines of at most 75 octets and terminate
+/// it with CRLF, as required by RFC 6350 section 3.2.
+///
+/// Fold points insert `\r\n` followed by a single space; multi-byte UTF-8
+/// characters are never split across a fold.
+fn fold_line(line: &str) -> String {
+    let mut out = String::new();
+    let mut octets = 0;
+    let mut first = true;
+    for c in line.chars() {
+        let clen = c.len_utf8();
+        // The leading space of a continuation line counts toward the 75.
+        let limit = if first { 75 } else { 74 };
+        if octets + clen > limit {
+            out.push_str("\r\n ");
+            octets = 0;
+            first = false;
+        }
+        out.push(c);
+        octets += clen;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// Fold a Quoted-Printable content line using `=`-soft breaks rather than
+/// RFC 6350 whitespace continuations.
+///
+/// QP decoders reassemble a value by deleting a trailing `=` and the following
+/// CRLF, so a fold must end with `=` and must never fall inside a `=XX` escape.
+/// The property part (up to the first `:`) stays on the first line.
+fn fold_quoted_printable(line: &str) -> String {
+    fn is_hex(b: u8) -> bool {
+        b.is_ascii_hexdigit()
+    }
+
+    let idx = match line.find(':') {
+        Some(i) => i,
+        None => return fold_line(line),
+    };
+    let (prefix, value) = (&line[..=idx], &line[idx + 1..]);
+    let bytes = value.as_bytes();
+
+    let mut out = String::from(prefix);
+    let mut cur = prefix.len();
+    let mut i = 0;
+    while i < bytes.len() {
+        let unit = if bytes[i] == b'=' && i + 2 < bytes.len() && is_hex(bytes[i + 1]) && is_hex(bytes[i + 2]) {
+            3
+        } else {
+            1
+        };
+        // Leave room for the trailing soft-break `=`, keeping physical lines
+        // within the 76-octet QP limit.
+        if cur + unit > 75 {
+            out.push_str("=\r\n");
+            cur = 0;
+        }
+        out.push_str(&value[i..i + unit]);
+        cur += unit;
+        i += unit;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// The canonical identity of a content line: group, property name, normalized
+/// parameters and the (possibly decoded) value.
+type CanonicalKey<'a> = (Option<String>, String, Vec<(String, Vec<String>)>, &'a str);
+
+/// A single parsed vCard content line (RFC 6350 section 3.3).
+#[derive(Debug, Clone)]
+struct ContentLine {
+    group: Option<String>,
+    name: String,
+    params: Vec<(String, Vec<String>)>,
+    value: String,
+    /// The value decoded to canonical UTF-8 when it carries a transfer
+    /// `ENCODING` (Quoted-Printable or Base64); used for comparison while
+    /// `raw` is kept for output.
+    decoded: Option<String>,
+    /// The original logical line, preserved verbatim for output.
+    raw: String,
+}
+
+impl ContentLine {
+    fn parse(line: &str) -> Self {
+        // Split the property part from the value at the first unquoted ':'.
+        let mut prop = line;
+        let mut value = "";
+        let mut quoted = false;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ':' if !quoted => {
+                    prop = &line[..i];
+                    value = &line[i + 1..];
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let mut segments = split_unquoted(prop, ';').into_iter();
+        let name_part = segments.next().unwrap_or_default();
+        let (group, name) = match name_part.find('.') {
+            Some(i) => (Some(name_part[..i].to_owned()), name_part[i + 1..].to_owned()),
+            None => (None, name_part),
+        };
+
+        let params: Vec<(String, Vec<String>)> = segments
+            .map(|seg| {
+                let (key, vals) = match seg.find('=') {
+                    Some(i) => (seg[..i].to_owned(), &seg[i + 1..]),
+                    None => (seg.clone(), ""),
+                };
+                let vals: Vec<String> = split_unquoted(vals, ',')
+                    .iter()
+                    .map(|v| decode_param_value(v.trim_matches('"')))
+                    .collect();
+                (key, vals)
+            })
+            .collect();
+
+        let decoded = match encoding(&params).as_deref() {
+            Some("QUOTED-PRINTABLE") => Some(decode_quoted_printable(value)),
+            Some("BASE64") | Some("B") => {
+                Some(String::from_utf8_lossy(&decode_base64(value)).into_owned())
+            }
+            _ => None,
+        };
+
+        ContentLine {
+            group,
+            name,
+            params,
+            value: value.to_owned(),
+            decoded,
+            raw: line.to_owned(),
+        }
+    }
+
+    /// Whether this line's value ends with a Quoted-Printable soft line break.
+    fn qp_soft_break(&self) -> bool {
+        encoding(&self.params).as_deref() == Some("QUOTED-PRINTABLE") && self.value.ends_with('=')
     }
 
     fn print(&self) {
-        for l in &self.lines {
-            println!("{}", l);
+        // Quoted-Printable values must fold with `=`-soft breaks, never the
+        // RFC 6350 whitespace continuation, which would corrupt a `=XX` escape.
+        if encoding(&self.params).as_deref() == Some("QUOTED-PRINTABLE") {
+            print!("{}", fold_quoted_printable(&self.raw));
+        } else {
+            print!("{}", fold_line(&self.raw));
         }
     }
+
+    /// A canonical key used for equality and hashing.
+    ///
+    /// Property and parameter names are case-insensitive and parameter and
+    /// multi-value ordering is not significant, so two lines that differ only
+    /// in those respects compare equal. Encoded values compare on their
+    /// decoded form so differently-encoded duplicates collapse. The original
+    /// text is kept in `raw` for output.
+    fn canonical(&self) -> CanonicalKey<'_> {
+        let mut params: Vec<(String, Vec<String>)> = self
+            .params
+            .iter()
+            // ENCODING affects only the wire form, not identity.
+            .filter(|(k, _)| !k.eq_ignore_ascii_case("ENCODING"))
+            .map(|(k, vs)| {
+                let mut vs = vs.clone();
+                vs.sort();
+                (k.to_uppercase(), vs)
+            })
+            .collect();
+        params.sort();
+        (
+            self.group.as_ref().map(|g| g.to_uppercase()),
+            self.name.to_uppercase(),
+            params,
+            self.decoded.as_deref().unwrap_or(&self.value),
+        )
+    }
+}
+
+impl PartialEq for ContentLine {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for ContentLine {}
+
+impl std::hash::Hash for ContentLine {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+/// Which property selects the key cards are merged on.
+#[derive(Debug, Clone, Copy)]
+enum KeyMode {
+    Uid,
+    N,
+    Fn,
 }
 
 struct Vcard {
     version: String,
-    content: HashSet<VcardEntry>,
+    content: HashSet<ContentLine>,
 }
 
 impl Vcard {
@@ -67,7 +407,12 @@ impl Vcard {
         self.version = s.to_owned();
     }
 
-    fn insert(&mut self, e: VcardEntry) {
+    /// Whether the card carries a VERSION we know how to handle.
+    fn version_supported(&self) -> bool {
+        matches!(self.version.as_str(), "2.1" | "3.0" | "4.0")
+    }
+
+    fn insert(&mut self, e: ContentLine) {
         self.content.insert(e);
     }
 
@@ -77,8 +422,30 @@ impl Vcard {
         }
     }
 
-    fn get(&self, key: &str) -> Option<&VcardEntry> {
-        self.content.iter().find(|e| e.lines[0].starts_with(key))
+    fn get(&self, key: &str) -> Option<&ContentLine> {
+        self.content.iter().find(|e| e.name.eq_ignore_ascii_case(key))
+    }
+
+    /// The value of the card's `UID` property, if present.
+    fn uid(&self) -> Option<&str> {
+        self.get("UID").map(|e| e.value.as_str())
+    }
+
+    /// The key used to group cards for merging.
+    ///
+    /// `UID` is the globally unique identifier (RFC 6350) and is preferred;
+    /// when it is absent the `N` and then `FN` properties are used as a
+    /// fallback. The `N`/`FN` modes select that property unconditionally.
+    fn merge_key(&self, mode: KeyMode) -> Option<String> {
+        match mode {
+            KeyMode::Uid => self
+                .uid()
+                .or_else(|| self.get("N").map(|e| e.value.as_str()))
+                .or_else(|| self.get("FN").map(|e| e.value.as_str()))
+                .map(|s| s.to_owned()),
+            KeyMode::N => self.get("N").map(|e| e.value.clone()),
+            KeyMode::Fn => self.get("FN").map(|e| e.value.clone()),
+        }
     }
 
     fn print(&self) {
@@ -96,17 +463,51 @@ struct Parser {
     cur_idx: usize,
 }
 
+/// Reconstruct logical lines from physical ones per RFC 6350 / RFC 2426.
+///
+/// A folded line is continued by a CRLF followed by a single space or
+/// horizontal tab; unfolding deletes the CRLF and exactly one following
+/// whitespace byte. `stream.lines()` already dropped the line breaks, so we
+/// only need to strip the one leading `' '` or `'\t'` and append the rest to
+/// the previous logical line.
+fn unfold(lines: Vec<String>) -> Vec<String> {
+    let mut logical: Vec<String> = Vec::new();
+    for line in lines {
+        if (line.starts_with(' ') || line.starts_with('\t')) && !logical.is_empty() {
+            logical.last_mut().unwrap().push_str(&line[1..]);
+        } else {
+            logical.push(line);
+        }
+    }
+    logical
+}
+
 impl Parser {
     fn new(stream: String) -> Self {
+        let lines = unfold(stream.lines().map(|s| s.to_string()).collect());
         Parser {
             cards: Vec::new(),
             cur_card: None,
 
-            lines: stream.lines().map(|s| s.to_string()).collect(),
+            lines,
             cur_idx: 0,
         }
     }
 
+    /// Build a `Parse` error pointing at the offending property on the current
+    /// line (the part up to the first `:`, or the whole line if there is none).
+    fn err(&self, msg: &str) -> Box<Error> {
+        let text = self.lines[self.cur_idx].clone();
+        let span = text.find(':').unwrap_or_else(|| text.chars().count());
+        Box::new(Error::Parse {
+            line: self.cur_idx + 1,
+            col: 1,
+            span,
+            text,
+            msg: msg.to_owned(),
+        })
+    }
+
     fn parse(mut self) -> Result<Vec<Vcard>> {
         while self.cur_idx < self.lines.len() {
             self.vcard()?;
@@ -126,36 +527,27 @@ impl Parser {
         match self.lines[self.cur_idx].as_str() {
             "BEGIN:VCARD" => {
                 if self.cur_card.is_some() {
-                    return Err(Box::new(Error::Format));
+                    return Err(self.err("unexpected nested BEGIN:VCARD"));
                 }
 
-                eprintln!("New card at line {}", self.cur_idx + 1);
                 self.cur_card = Some(Vcard::new());
                 self.cur_idx += 1;
-                self.version()?;
                 Ok(())
             }
-            _ => Err(Box::new(Error::Format)),
-        }
-    }
-
-    fn version(&mut self) -> Result<()> {
-        if self.lines[self.cur_idx].starts_with("VERSION:") {
-            self.cur_card
-                .as_mut()
-                .unwrap()
-                .set_version(self.lines[self.cur_idx].as_str());
-            self.cur_idx += 1;
-            Ok(())
-        } else {
-            Err(Box::new(Error::Format))
+            _ => Err(self.err("property before BEGIN:VCARD")),
         }
     }
 
     fn end(&mut self) -> Result<bool> {
         match self.lines[self.cur_idx].as_str() {
             "END:VCARD" => {
-                self.cards.push(self.cur_card.take().unwrap());
+                let card = self.cur_card.take().unwrap();
+                // VERSION may appear anywhere inside the card (v3 allows it);
+                // validate once the whole card has been read.
+                if !card.version_supported() {
+                    return Err(self.err("card has missing or unsupported VERSION"));
+                }
+                self.cards.push(card);
                 self.cur_idx += 1;
                 Ok(true)
             }
@@ -164,20 +556,65 @@ impl Parser {
     }
 
     fn entry(&mut self) -> Result<()> {
-        let mut entry = VcardEntry::new();
-        entry.push(self.lines[self.cur_idx].as_str());
+        let start = self.cur_idx;
+        let mut raw = self.lines[self.cur_idx].clone();
         self.cur_idx += 1;
-        while self.lines[self.cur_idx].starts_with(' ') {
-            entry.push(self.lines[self.cur_idx].as_str());
+        let mut entry = ContentLine::parse(&raw);
+        // vCard 2.1 Quoted-Printable values continue across physical lines via
+        // a trailing `=`; join them back into one logical line. Never consume a
+        // BEGIN/END marker as a continuation — a stray trailing `=` before one
+        // is a malformed value, not a soft break.
+        while entry.qp_soft_break()
+            && self.cur_idx < self.lines.len()
+            && !self.lines[self.cur_idx].starts_with("BEGIN:")
+            && !self.lines[self.cur_idx].starts_with("END:")
+        {
+            raw.pop();
+            raw.push_str(&self.lines[self.cur_idx]);
             self.cur_idx += 1;
+            entry = ContentLine::parse(&raw);
+        }
+        if entry.qp_soft_break() {
+            return Err(Box::new(Error::Parse {
+                line: start + 1,
+                col: 1,
+                span: self.lines[start].chars().count(),
+                text: self.lines[start].clone(),
+                msg: "Quoted-Printable soft break with no continuation line".to_owned(),
+            }));
+        }
+        let card = self.cur_card.as_mut().unwrap();
+        // VERSION is a normal property that we promote onto the card instead of
+        // storing it alongside the other content lines.
+        if entry.name.eq_ignore_ascii_case("VERSION") {
+            card.set_version(&entry.value);
+        } else {
+            card.insert(entry);
         }
-        self.cur_card.as_mut().unwrap().insert(entry);
         Ok(())
     }
 }
 
-fn main() -> Result<()> {
-    let infile = env::args().nth(1);
+fn run() -> Result<()> {
+    let mut mode = KeyMode::Uid;
+    let mut infile = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => {
+                mode = match args.next().as_deref() {
+                    Some("uid") => KeyMode::Uid,
+                    Some("n") => KeyMode::N,
+                    Some("fn") => KeyMode::Fn,
+                    _ => {
+                        usage();
+                        return Err(Box::new(Error::Usage));
+                    }
+                };
+            }
+            _ => infile = Some(arg),
+        }
+    }
     if infile.is_none() {
         usage();
         return Err(Box::new(Error::Usage));
@@ -189,22 +626,114 @@ fn main() -> Result<()> {
         parser.parse()?
     };
 
-    let mut collection: HashMap<VcardEntry, Vcard> = HashMap::new();
+    // Key on (merge key, version): cards that share a key but differ in version
+    // are kept as distinct output cards rather than merged or discarded.
+    let mut collection: HashMap<(String, String), Vcard> = HashMap::new();
     for c in cards {
-        if let Some(name) = c.get("N") {
+        if let Some(key) = c.merge_key(mode) {
             collection
-                .entry(name.clone())
+                .entry((key, c.version.clone()))
                 .and_modify(|e| e.extend(&c))
                 .or_insert(c);
         }
     }
 
     for c in collection.values() {
-        println!("BEGIN:VCARD");
-        println!("{}", c.version);
+        print!("{}", fold_line("BEGIN:VCARD"));
+        print!("{}", fold_line(&format!("VERSION:{}", c.version)));
         c.print();
-        println!("END:VCARD");
+        print!("{}", fold_line("END:VCARD"));
     }
 
     Ok(())
 }
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_printable_decoding() {
+        assert_eq!(decode_quoted_printable("Caf=C3=A9"), "Café");
+        // =0D=0A decodes to a CRLF.
+        assert_eq!(decode_quoted_printable("a=0D=0Ab"), "a\r\nb");
+    }
+
+    #[test]
+    fn base64_decoding() {
+        assert_eq!(decode_base64("aGVsbG8="), b"hello");
+        assert_eq!(decode_base64("Zm9vYmFy"), b"foobar");
+    }
+
+    #[test]
+    fn caret_decoding() {
+        assert_eq!(decode_param_value("a^nb^^c^'"), "a\nb^c\"");
+        // A trailing lone caret is left as-is.
+        assert_eq!(decode_param_value("x^"), "x^");
+    }
+
+    #[test]
+    fn unfold_joins_space_and_tab_continuations() {
+        let lines = vec![
+            "NOTE:hello".to_string(),
+            " world".to_string(),
+            "\tagain".to_string(),
+        ];
+        // The continuation whitespace is the fold marker and is removed.
+        assert_eq!(unfold(lines), vec!["NOTE:helloworldagain".to_string()]);
+    }
+
+    #[test]
+    fn fold_respects_75_octets_without_splitting_chars() {
+        // 40 two-byte characters = 80 octets, forcing a fold.
+        let value = "é".repeat(40);
+        let folded = fold_line(&value);
+        assert!(folded.ends_with("\r\n"));
+        assert!(folded.contains("\r\n "));
+        for physical in folded.trim_end_matches("\r\n").split("\r\n") {
+            assert!(physical.len() <= 75, "{:?} exceeds 75 octets", physical);
+        }
+        // Unfolding the output reproduces the original value.
+        let rejoined: String = folded
+            .trim_end_matches("\r\n")
+            .split("\r\n ")
+            .collect::<Vec<_>>()
+            .join("");
+        assert_eq!(rejoined, value);
+    }
+
+    #[test]
+    fn quoted_printable_fold_preserves_escapes() {
+        let line = format!("NOTE;ENCODING=QUOTED-PRINTABLE:{}", "=C3=A9".repeat(20));
+        let folded = fold_quoted_printable(&line);
+        for physical in folded.trim_end_matches("\r\n").split("\r\n") {
+            assert!(physical.len() <= 76);
+            // No physical line ends mid-escape.
+            assert!(!physical.trim_end_matches('=').ends_with("=C"));
+            let body = physical.strip_suffix('=').unwrap_or(physical);
+            assert_eq!(body.rsplit('=').next().map(str::len), Some(2));
+        }
+    }
+
+    #[test]
+    fn dedup_ignores_parameter_order() {
+        let a = ContentLine::parse("TEL;TYPE=HOME,VOICE:123");
+        let b = ContentLine::parse("TEL;TYPE=VOICE,HOME:123");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn merge_key_is_case_insensitive() {
+        let mut card = Vcard::new();
+        card.insert(ContentLine::parse("uid:shared"));
+        assert_eq!(card.merge_key(KeyMode::Uid), Some("shared".to_string()));
+        assert_eq!(card.uid(), Some("shared"));
+    }
+}